@@ -1,33 +1,119 @@
-use std::sync::Mutex;
+use std::{collections::HashMap, fmt, sync::Mutex};
 
 use actix_web::{
-    delete, get, post, put,
+    delete, get,
+    http::StatusCode,
+    patch, post, put,
     web::{Data, Json, Path, Query, ServiceConfig},
-    HttpResponse, Responder,
+    HttpRequest, HttpResponse, Responder, ResponseError,
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use utoipa::{ToSchema, IntoParams};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
 
-use crate::{LogApiKey, RequireApiKey};
+pub(super) use auth::{Action, AuthConfig, AuthController, Key, Policies};
+use auth::{
+    bearer_token, can_reveal_secrets, Authenticated, CanCreateConfigs, CanDeleteConfigs,
+    CanGetConfigs, CanManageKeys, CanSearch, CanUpdateConfigs, GuardedData, Public,
+};
+
+mod auth;
+mod render;
+mod secret;
+
+const REDACTED_SECRET: &str = "********";
 
-#[derive(Default)]
 pub(super) struct ConfigStore {
     configs: Mutex<Vec<Config>>,
+    secret_key: [u8; 32],
 }
 
-pub(super) fn configure(store: Data<ConfigStore>) -> impl FnOnce(&mut ServiceConfig) {
+impl ConfigStore {
+    /// `secret_passphrase` is stretched into a 32-byte AES-256 key via SHA-256, so it can be
+    /// any length, the same way `AuthController`'s master key accepts any length for its HMAC.
+    pub(super) fn new(secret_passphrase: impl AsRef<[u8]>) -> Self {
+        Self {
+            configs: Mutex::new(Vec::new()),
+            secret_key: secret::derive_key(secret_passphrase.as_ref()),
+        }
+    }
+
+    /// Returns `config` with its `value` decrypted if `reveal_secrets`, or redacted otherwise.
+    /// Non-secret configs are returned unchanged either way.
+    fn present(&self, mut config: Config, reveal_secrets: bool) -> Config {
+        if config.secret {
+            config.value = if reveal_secrets {
+                secret::decrypt(&self.secret_key, &config.value).unwrap_or_default()
+            } else {
+                String::from(REDACTED_SECRET)
+            };
+        }
+
+        config
+    }
+
+    /// Encrypts `value` for at-rest storage if `config` is marked secret, leaving it untouched
+    /// otherwise.
+    fn seal(&self, config: &mut Config) {
+        if config.secret {
+            config.value = secret::encrypt(&self.secret_key, &config.value);
+        }
+    }
+
+    /// Build the `key -> value` context a template resolves placeholders against, applying the
+    /// same secret redaction rules as the plain getters.
+    fn context(&self, configs: &[Config], reveal_secrets: bool) -> HashMap<String, String> {
+        configs
+            .iter()
+            .cloned()
+            .map(|config| {
+                let key = config.key.clone();
+                (key, self.present(config, reveal_secrets).value)
+            })
+            .collect()
+    }
+}
+
+pub(super) fn configure(
+    store: Data<ConfigStore>,
+    auth: Data<AuthController>,
+    auth_config: Data<AuthConfig>,
+) -> impl FnOnce(&mut ServiceConfig) {
     |config: &mut ServiceConfig| {
         config
             .app_data(store)
+            .app_data(auth)
+            .app_data(auth_config)
+            .service(health)
+            .service(whoami)
             .service(search_configs)
             .service(get_configs)
             .service(create_config)
             .service(delete_config)
             .service(get_config_by_id)
-            .service(update_config);
+            .service(render_config)
+            .service(update_config)
+            .service(list_keys)
+            .service(create_key)
+            .service(update_key)
+            .service(delete_key);
     }
 }
 
+/// Health check.
+///
+/// Always returns 200 if the service is up; requires no bearer token.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Service is up")
+    )
+)]
+#[get("/health")]
+pub(super) async fn health(_store: GuardedData<Public, Data<ConfigStore>>) -> impl Responder {
+    HttpResponse::Ok().finish()
+}
+
 /// Task to do.
 #[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
 pub(super) struct Config {
@@ -43,6 +129,10 @@ pub(super) struct Config {
     /// Value of the config
     #[schema(example = "Top Shelf")]
     value: String,
+    /// Whether the value is a secret, encrypted at rest and redacted to `"********"` unless
+    /// the caller's key grants `SecretsReveal`.
+    #[serde(default)]
+    secret: bool,
 }
 
 /// Request to update existing `Config` item.`
@@ -56,7 +146,7 @@ pub(super) struct ConfigUpdateRequest {
 }
 
 /// config endpoint error responses
-#[derive(Serialize, Deserialize, Clone, ToSchema)]
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
 pub(super) enum ErrorResponse {
     /// When Config is not found by search term.
     NotFound(String),
@@ -64,72 +154,187 @@ pub(super) enum ErrorResponse {
     Conflict(String),
     /// When config endpoint was called without correct credentials
     Unauthorized(String),
+    /// When a request body fails validation; one entry per violation.
+    Validation(Vec<String>),
+}
+
+impl ErrorResponse {
+    pub(super) fn not_found(detail: impl Into<String>) -> Self {
+        Self::NotFound(detail.into())
+    }
+
+    pub(super) fn conflict(detail: impl Into<String>) -> Self {
+        Self::Conflict(detail.into())
+    }
+
+    pub(super) fn unauthorized(detail: impl Into<String>) -> Self {
+        Self::Unauthorized(detail.into())
+    }
+
+    pub(super) fn validation(errors: Vec<String>) -> Self {
+        Self::Validation(errors)
+    }
+}
+
+impl fmt::Display for ErrorResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(detail) => write!(f, "not found: {detail}"),
+            Self::Conflict(detail) => write!(f, "conflict: {detail}"),
+            Self::Unauthorized(detail) => write!(f, "unauthorized: {detail}"),
+            Self::Validation(errors) => write!(f, "validation failed: {}", errors.join(", ")),
+        }
+    }
+}
+
+impl std::error::Error for ErrorResponse {}
+
+impl ResponseError for ErrorResponse {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(self)
+    }
+}
+
+/// Rejects an empty `key` or `value`, collecting every violation instead of stopping at the
+/// first so callers see everything wrong with the request at once.
+fn validate_config(key: &str, value: &str) -> Result<(), ErrorResponse> {
+    let mut errors = Vec::new();
+
+    if key.trim().is_empty() {
+        errors.push(String::from("key must not be empty"));
+    }
+    if value.trim().is_empty() {
+        errors.push(String::from("value must not be empty"));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ErrorResponse::validation(errors))
+    }
+}
+
+/// Query parameters accepted by the plain `Config` getters.
+#[derive(Deserialize, Debug, IntoParams)]
+pub(super) struct RenderQuery {
+    /// When `true`, resolve `{{ other_key }}` placeholders in the returned value(s) against the
+    /// rest of the config store. Items that fail to render (unknown key or cycle) are returned
+    /// with their stored value unchanged; use `GET /config/{id}/render` to get an error instead.
+    #[serde(default)]
+    render: bool,
 }
 
 /// Get list of Configs.
 ///
-/// List configs from in-memory config store.
+/// List configs from in-memory config store. Requires the `ConfigsGet` action.
 ///
 /// One could call the api endpoint with following curl.
 /// ```text
-/// curl localhost:8080/config
+/// curl -H "Authorization: Bearer <token>" localhost:8080/config
 /// ```
 #[utoipa::path(
+    params(
+        RenderQuery
+    ),
     responses(
         (status = 200, description = "List current config items", body = [Config])
+    ),
+    security(
+        ("api_key" = [])
     )
 )]
 #[get("/config")]
-pub(super) async fn get_configs(config_store: Data<ConfigStore>) -> impl Responder {
+pub(super) async fn get_configs(
+    req: HttpRequest,
+    query: Query<RenderQuery>,
+    config_store: GuardedData<CanGetConfigs, Data<ConfigStore>>,
+) -> impl Responder {
+    let reveal_secrets = can_reveal_secrets(&req);
     let configs = config_store.configs.lock().unwrap();
+    let context = query.render.then(|| config_store.context(&configs, reveal_secrets));
 
-    HttpResponse::Ok().json(configs.clone())
+    HttpResponse::Ok().json(
+        configs
+            .iter()
+            .cloned()
+            .map(|config| {
+                let mut config = config_store.present(config, reveal_secrets);
+
+                if let Some(context) = &context {
+                    if let Ok(value) = render::resolve(context, &config.key, &config.value) {
+                        config.value = value;
+                    }
+                }
+
+                config
+            })
+            .collect::<Vec<_>>(),
+    )
 }
 
 /// Create new config to shared in-memory storage.
 ///
 /// Post a new `config` in request body as json to store it. Api will return
 /// created `config` on success or `ErrorResponse::Conflict` if config with same id already exists.
+/// Requires the `ConfigsCreate` action.
 ///
 /// One could call the api with.
 /// ```text
-/// curl localhost:8080/config -d '{"id": 1, "desc": "chain name", "key": "chain", "value": "top shelf"}'
+/// curl -H "Authorization: Bearer <token>" localhost:8080/config -d '{"id": 1, "desc": "chain name", "key": "chain", "value": "top shelf"}'
 /// ```
 #[utoipa::path(
     request_body = Config,
     responses(
         (status = 201, description = "Config created successfully", body = Config),
-        (status = 409, description = "Config with id already exists", body = ErrorResponse, example = json!(ErrorResponse::Conflict(String::from("id = 1"))))
+        (status = 409, description = "Config with id already exists", body = ErrorResponse, example = json!(ErrorResponse::Conflict(String::from("id = 1")))),
+        (status = 422, description = "Config failed validation", body = ErrorResponse, example = json!(ErrorResponse::Validation(vec![String::from("key must not be empty")])))
+    ),
+    security(
+        ("api_key" = [])
     )
 )]
 #[post("/config")]
-pub(super) async fn create_config(config: Json<Config>, config_store: Data<ConfigStore>) -> impl Responder {
+pub(super) async fn create_config(
+    req: HttpRequest,
+    config: Json<Config>,
+    config_store: GuardedData<CanCreateConfigs, Data<ConfigStore>>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let reveal_secrets = can_reveal_secrets(&req);
     let mut configs = config_store.configs.lock().unwrap();
-    let config = &config.into_inner();
+    let requested = config.into_inner();
 
-    configs
-        .iter()
-        .find(|existing| existing.id == config.id)
-        .map(|existing| {
-            HttpResponse::Conflict().json(ErrorResponse::Conflict(format!("id = {}", existing.id)))
-        })
-        .unwrap_or_else(|| {
-            configs.push(config.clone());
-
-            HttpResponse::Ok().json(config)
-        })
+    validate_config(&requested.key, &requested.value)?;
+
+    if let Some(existing) = configs.iter().find(|existing| existing.id == requested.id) {
+        return Err(ErrorResponse::conflict(format!("id = {}", existing.id)));
+    }
+
+    let mut stored = requested;
+    config_store.seal(&mut stored);
+    configs.push(stored.clone());
+
+    Ok(HttpResponse::Created().json(config_store.present(stored, reveal_secrets)))
 }
 
 /// Delete config by given path variable id.
 ///
-/// This endpoint needs `api_key` authentication in order to call. Api key can be found from README.md.
+/// This endpoint requires a bearer token whose key grants the `ConfigsDelete` action.
 ///
 /// Api will delete config from shared in-memory storage by the provided id and return success 200.
 /// If storage does not contain `config` with given id 404 not found will be returned.
 #[utoipa::path(
     responses(
         (status = 200, description = "Config deleted successfully"),
-        (status = 401, description = "Unauthorized to delete Config", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing api key")))),
+        (status = 401, description = "Unauthorized to delete Config", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing or invalid api key")))),
         (status = 404, description = "Config not found by id", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("id = 1"))))
     ),
     params(
@@ -139,8 +344,11 @@ pub(super) async fn create_config(config: Json<Config>, config_store: Data<Confi
         ("api_key" = [])
     )
 )]
-#[delete("/config/{id}", wrap = "RequireApiKey")]
-pub(super) async fn delete_config(id: Path<i32>, config_store: Data<ConfigStore>) -> impl Responder {
+#[delete("/config/{id}")]
+pub(super) async fn delete_config(
+    id: Path<i32>,
+    config_store: GuardedData<CanDeleteConfigs, Data<ConfigStore>>,
+) -> Result<HttpResponse, ErrorResponse> {
     let mut configs = config_store.configs.lock().unwrap();
     let id = id.into_inner();
 
@@ -151,42 +359,111 @@ pub(super) async fn delete_config(id: Path<i32>, config_store: Data<ConfigStore>
         .collect::<Vec<_>>();
 
     if new_configs.len() == configs.len() {
-        HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("id = {id}")))
-    } else {
-        *configs = new_configs;
-        HttpResponse::Ok().finish()
+        return Err(ErrorResponse::not_found(format!("id = {id}")));
     }
+
+    *configs = new_configs;
+    Ok(HttpResponse::Ok().finish())
 }
 
 /// Get by given id.
 ///
 /// Return found `Config` with status 200 or 404 not found if `config` is not found from shared in-memory storage.
+/// Requires the `ConfigsGet` action.
 #[utoipa::path(
+    params(
+        ("id", description = "Unique storage id of Config"),
+        RenderQuery
+    ),
     responses(
         (status = 200, description = "Config found from storage", body = Config),
         (status = 404, description = "Config not found by id", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("id = 1"))))
     ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/config/{id}")]
+pub(super) async fn get_config_by_id(
+    req: HttpRequest,
+    id: Path<i32>,
+    query: Query<RenderQuery>,
+    config_store: GuardedData<CanGetConfigs, Data<ConfigStore>>,
+) -> Result<Json<Config>, ErrorResponse> {
+    let reveal_secrets = can_reveal_secrets(&req);
+    let configs = config_store.configs.lock().unwrap();
+    let id = id.into_inner();
+
+    let config = configs
+        .iter()
+        .find(|config| config.id == id)
+        .cloned()
+        .ok_or_else(|| ErrorResponse::not_found(format!("id = {id}")))?;
+
+    let mut config = config_store.present(config, reveal_secrets);
+
+    if query.render {
+        let context = config_store.context(&configs, reveal_secrets);
+        if let Ok(value) = render::resolve(&context, &config.key, &config.value) {
+            config.value = value;
+        }
+    }
+
+    Ok(Json(config))
+}
+
+/// Render a config's value, resolving `{{ other_key }}` placeholders against the rest of the
+/// config store.
+///
+/// References may chain (`a` references `b` references `c`), so resolution repeats until no
+/// placeholders remain. An unknown referenced key or a reference cycle is reported as an error
+/// naming the offending key rather than silently rendering an empty string. Requires the
+/// `ConfigsGet` action.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Config rendered successfully", body = Config),
+        (status = 404, description = "Config not found by id, or render referenced an unknown key", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("key = other_key")))),
+        (status = 409, description = "Render referenced keys form a cycle", body = ErrorResponse, example = json!(ErrorResponse::Conflict(String::from("key = other_key"))))
+    ),
     params(
         ("id", description = "Unique storage id of Config")
+    ),
+    security(
+        ("api_key" = [])
     )
 )]
-#[get("/config/{id}")]
-pub(super) async fn get_config_by_id(id: Path<i32>, config_store: Data<ConfigStore>) -> impl Responder {
+#[get("/config/{id}/render")]
+pub(super) async fn render_config(
+    req: HttpRequest,
+    id: Path<i32>,
+    config_store: GuardedData<CanGetConfigs, Data<ConfigStore>>,
+) -> Result<Json<Config>, ErrorResponse> {
+    let reveal_secrets = can_reveal_secrets(&req);
     let configs = config_store.configs.lock().unwrap();
     let id = id.into_inner();
 
-    configs
+    let config = configs
         .iter()
         .find(|config| config.id == id)
-        .map(|config| HttpResponse::Ok().json(config))
-        .unwrap_or_else(|| {
-            HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("id = {id}")))
-        })
+        .cloned()
+        .ok_or_else(|| ErrorResponse::not_found(format!("id = {id}")))?;
+
+    let mut config = config_store.present(config, reveal_secrets);
+    let context = config_store.context(&configs, reveal_secrets);
+
+    let value = render::resolve(&context, &config.key, &config.value).map_err(|err| match err {
+        render::RenderError::UnknownKey(key) => ErrorResponse::not_found(format!("key = {key}")),
+        render::RenderError::Cycle(key) => ErrorResponse::conflict(format!("key = {key}")),
+    })?;
+
+    config.value = value;
+
+    Ok(Json(config))
 }
 
 /// Update config with given id.
 ///
-/// This endpoint supports optional authentication.
+/// This endpoint requires a bearer token whose key grants the `ConfigsUpdate` action.
 ///
 /// Tries to update `config` by given id as path variable. If config is found by id values are
 /// updated according `configUpdateRequest` and updated `config` is returned with status 200.
@@ -195,39 +472,53 @@ pub(super) async fn get_config_by_id(id: Path<i32>, config_store: Data<ConfigSto
     request_body = ConfigUpdateRequest,
     responses(
         (status = 200, description = "Config updated successfully", body = config),
-        (status = 404, description = "Config not found by id", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("id = 1"))))
+        (status = 404, description = "Config not found by id", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("id = 1")))),
+        (status = 422, description = "Config failed validation", body = ErrorResponse, example = json!(ErrorResponse::Validation(vec![String::from("value must not be empty")])))
     ),
     params(
         ("id", description = "Unique storage id of Config")
     ),
     security(
-        (),
         ("api_key" = [])
     )
 )]
-#[put("/config/{id}", wrap = "LogApiKey")]
+#[put("/config/{id}")]
 pub(super) async fn update_config(
+    req: HttpRequest,
     id: Path<i32>,
     config: Json<ConfigUpdateRequest>,
-    config_store: Data<ConfigStore>,
-) -> impl Responder {
+    config_store: GuardedData<CanUpdateConfigs, Data<ConfigStore>>,
+) -> Result<Json<Config>, ErrorResponse> {
+    let reveal_secrets = can_reveal_secrets(&req);
     let mut configs = config_store.configs.lock().unwrap();
     let id = id.into_inner();
-    let config = config.into_inner();
+    let request = config.into_inner();
 
-    configs
+    let existing = configs
         .iter_mut()
-        .find_map(|c| if c.id == id { Some(c) } else { None })
-        .map(|existing| {
-            if let Some(value) = config.value {
-                existing.value = value;
-            }
-
-            HttpResponse::Ok().json(existing)
-        })
-        .unwrap_or_else(|| {
-            HttpResponse::NotFound().json(ErrorResponse::NotFound(format!("id = {id}")))
-        })
+        .find(|c| c.id == id)
+        .ok_or_else(|| ErrorResponse::not_found(format!("id = {id}")))?;
+
+    let mut plaintext = if existing.secret {
+        secret::decrypt(&config_store.secret_key, &existing.value).unwrap_or_default()
+    } else {
+        existing.value.clone()
+    };
+
+    if let Some(value) = request.value {
+        plaintext = value;
+    }
+
+    validate_config(&existing.key, &plaintext)?;
+
+    existing.secret = request.secret.unwrap_or(existing.secret);
+    existing.value = if existing.secret {
+        secret::encrypt(&config_store.secret_key, &plaintext)
+    } else {
+        plaintext
+    };
+
+    Ok(Json(config_store.present(existing.clone(), reveal_secrets)))
 }
 
 /// Search configs Query
@@ -240,19 +531,24 @@ pub(super) struct SearchConfigs {
 /// Search configs with by value
 ///
 /// Perform search from `config`s present in in-memory storage by matching config's value to
-/// value provided as query parameter. Returns 200 and matching `config` items.
+/// value provided as query parameter. Returns 200 and matching `config` items. Requires the
+/// `Search` action. Secret configs never participate in the match, so their values can't be
+/// discovered by probing the search query.
 #[utoipa::path(
     params(
         SearchConfigs
     ),
     responses(
         (status = 200, description = "Search did not result error", body = [Config]),
+    ),
+    security(
+        ("api_key" = [])
     )
 )]
 #[get("/config/search")]
 pub(super) async fn search_configs(
     query: Query<SearchConfigs>,
-    configs_store: Data<ConfigStore>,
+    configs_store: GuardedData<CanSearch, Data<ConfigStore>>,
 ) -> impl Responder {
     let configs = configs_store.configs.lock().unwrap();
 
@@ -260,11 +556,172 @@ pub(super) async fn search_configs(
         configs
             .iter()
             .filter(|config| {
-                config.value
-                    .to_lowercase()
-                    .contains(&query.value.to_lowercase())
+                !config.secret
+                    && config
+                        .value
+                        .to_lowercase()
+                        .contains(&query.value.to_lowercase())
             })
             .cloned()
             .collect::<Vec<_>>(),
     )
+}
+
+/// Request to create a new `Key`.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub(super) struct CreateKeyRequest {
+    /// Optional human readable name for the key.
+    #[schema(example = "CI deploy key")]
+    name: Option<String>,
+    /// Actions this key is allowed to perform.
+    actions: Vec<Action>,
+    /// Optional expiry, after which the key is rejected.
+    expires_at: Option<DateTime<Utc>>,
+}
+
+/// Request to update an existing `Key`.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub(super) struct UpdateKeyRequest {
+    /// New name for the key, left unchanged if omitted.
+    name: Option<String>,
+    /// New action set for the key, left unchanged if omitted.
+    actions: Option<Vec<Action>>,
+    /// New expiry for the key, left unchanged if omitted.
+    expires_at: Option<Option<DateTime<Utc>>>,
+}
+
+/// A `Key` together with the bearer token clients present as `Authorization: Bearer <token>`.
+#[derive(Serialize, ToSchema)]
+pub(super) struct KeyResponse {
+    #[serde(flatten)]
+    #[schema(inline)]
+    key: Key,
+    #[schema(example = "c7a1...e40f")]
+    token: String,
+}
+
+/// Resolve the key that authenticated this request.
+///
+/// Requires any valid, non-expired key; unlike the other `/keys` routes, no specific action is
+/// needed, so a key can always look up its own details.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The requesting key", body = Key),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorResponse, example = json!(ErrorResponse::Unauthorized(String::from("missing or invalid api key"))))
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/keys/me")]
+pub(super) async fn whoami(
+    req: HttpRequest,
+    auth: GuardedData<Authenticated, Data<AuthController>>,
+) -> Result<Json<Key>, ErrorResponse> {
+    let token = bearer_token(&req).unwrap_or_default();
+
+    auth.authenticate(&token)
+        .map(Json)
+        .ok_or_else(|| ErrorResponse::unauthorized(String::from("missing or invalid api key")))
+}
+
+/// List the keys in the key store.
+///
+/// Requires the `KeysManage` action.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "List current keys", body = [Key])
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/keys")]
+pub(super) async fn list_keys(
+    auth: GuardedData<CanManageKeys, Data<AuthController>>,
+) -> impl Responder {
+    HttpResponse::Ok().json(auth.list_keys())
+}
+
+/// Create a new key with the given name, actions and optional expiry.
+///
+/// Requires the `KeysManage` action.
+#[utoipa::path(
+    request_body = CreateKeyRequest,
+    responses(
+        (status = 201, description = "Key created successfully", body = KeyResponse)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/keys")]
+pub(super) async fn create_key(
+    request: Json<CreateKeyRequest>,
+    auth: GuardedData<CanManageKeys, Data<AuthController>>,
+) -> impl Responder {
+    let request = request.into_inner();
+    let key = auth.create_key(request.name, request.actions, request.expires_at);
+    let token = auth.reveal_token(&key);
+
+    HttpResponse::Created().json(KeyResponse { key, token })
+}
+
+/// Update the name, actions or expiry of an existing key by its `uid`.
+///
+/// Requires the `KeysManage` action.
+#[utoipa::path(
+    request_body = UpdateKeyRequest,
+    responses(
+        (status = 200, description = "Key updated successfully", body = Key),
+        (status = 404, description = "Key not found by uid", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("uid = 9d2d4f1c-5a3b-4b8a-9c1d-6f2e7a9b0c3d"))))
+    ),
+    params(
+        ("uid", description = "Unique id of the Key")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[patch("/keys/{uid}")]
+pub(super) async fn update_key(
+    uid: Path<Uuid>,
+    request: Json<UpdateKeyRequest>,
+    auth: GuardedData<CanManageKeys, Data<AuthController>>,
+) -> Result<Json<Key>, ErrorResponse> {
+    let uid = uid.into_inner();
+    let request = request.into_inner();
+
+    auth.update_key(uid, request.name, request.actions, request.expires_at)
+        .map(Json)
+        .ok_or_else(|| ErrorResponse::not_found(format!("uid = {uid}")))
+}
+
+/// Delete a key by its `uid`.
+///
+/// Requires the `KeysManage` action.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Key deleted successfully"),
+        (status = 404, description = "Key not found by uid", body = ErrorResponse, example = json!(ErrorResponse::NotFound(String::from("uid = 9d2d4f1c-5a3b-4b8a-9c1d-6f2e7a9b0c3d"))))
+    ),
+    params(
+        ("uid", description = "Unique id of the Key")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[delete("/keys/{uid}")]
+pub(super) async fn delete_key(
+    uid: Path<Uuid>,
+    auth: GuardedData<CanManageKeys, Data<AuthController>>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let uid = uid.into_inner();
+
+    if auth.delete_key(uid) {
+        Ok(HttpResponse::Ok().finish())
+    } else {
+        Err(ErrorResponse::not_found(format!("uid = {uid}")))
+    }
 }
\ No newline at end of file
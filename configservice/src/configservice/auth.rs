@@ -0,0 +1,442 @@
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    marker::PhantomData,
+    ops::Deref,
+    sync::Mutex,
+};
+
+use actix_web::{dev::Payload, http::header, web::Data, FromRequest, HttpRequest};
+use chrono::{DateTime, Utc};
+use futures::future::LocalBoxFuture;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::ErrorResponse;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Capability required to call a route, modeled on the MeiliSearch key spec.
+///
+/// A [`Key`] authorizes a request when its `actions` contain the route's required `Action`
+/// or [`Action::All`].
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, ToSchema)]
+pub(super) enum Action {
+    /// Grants every action below.
+    #[serde(rename = "*")]
+    All,
+    ConfigsGet,
+    ConfigsCreate,
+    ConfigsDelete,
+    ConfigsUpdate,
+    Search,
+    KeysManage,
+    SecretsReveal,
+}
+
+/// An API key, scoped to a set of [`Action`]s and optionally expiring.
+///
+/// The token a client presents as `Authorization: Bearer <token>` is never stored; it is
+/// re-derived on demand as `hex(HMAC-SHA256(master_key, uid))` so keys can be regenerated by
+/// rotating the master key without touching this struct.
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub(super) struct Key {
+    #[schema(example = "9d2d4f1c-5a3b-4b8a-9c1d-6f2e7a9b0c3d")]
+    pub(super) uid: Uuid,
+    #[schema(example = "Default Admin Key")]
+    pub(super) name: Option<String>,
+    pub(super) actions: Vec<Action>,
+    pub(super) expires_at: Option<DateTime<Utc>>,
+    pub(super) created_at: DateTime<Utc>,
+    pub(super) updated_at: DateTime<Utc>,
+}
+
+fn derive_token(master_key: &[u8], uid: Uuid) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(master_key).expect("HMAC-SHA256 accepts a key of any length");
+    mac.update(uid.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Holds the key store and authorizes requests against it.
+///
+/// Mirrors [`super::ConfigStore`]: an in-memory `Mutex<HashMap<Uuid, Key>>` is enough for this
+/// demo service, with the master key used only to derive and verify bearer tokens.
+pub(super) struct AuthController {
+    master_key: Vec<u8>,
+    keys: Mutex<HashMap<Uuid, Key>>,
+}
+
+impl AuthController {
+    /// Build a controller seeded with a default admin key granting [`Action::All`].
+    ///
+    /// The admin key's token is only logged at `debug` level, since it grants [`Action::All`]
+    /// and the default log filter is `info`; an operator who needs to bootstrap further keys
+    /// through the `/keys` routes can enable debug logging to retrieve it.
+    pub(super) fn new(master_key: impl Into<Vec<u8>>) -> Self {
+        let master_key = master_key.into();
+        let now = Utc::now();
+        let admin_key = Key {
+            uid: Uuid::nil(),
+            name: Some(String::from("Default Admin Key")),
+            actions: vec![Action::All],
+            expires_at: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        log::debug!(
+            "default admin key token: {}",
+            derive_token(&master_key, admin_key.uid)
+        );
+
+        let mut keys = HashMap::new();
+        keys.insert(admin_key.uid, admin_key);
+
+        Self {
+            master_key,
+            keys: Mutex::new(keys),
+        }
+    }
+
+    fn token_for(&self, uid: Uuid) -> String {
+        derive_token(&self.master_key, uid)
+    }
+
+    pub(super) fn create_key(
+        &self,
+        name: Option<String>,
+        actions: Vec<Action>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Key {
+        let now = Utc::now();
+        let key = Key {
+            uid: Uuid::new_v4(),
+            name,
+            actions,
+            expires_at,
+            created_at: now,
+            updated_at: now,
+        };
+
+        self.keys.lock().unwrap().insert(key.uid, key.clone());
+
+        key
+    }
+
+    pub(super) fn list_keys(&self) -> Vec<Key> {
+        self.keys.lock().unwrap().values().cloned().collect()
+    }
+
+    pub(super) fn update_key(
+        &self,
+        uid: Uuid,
+        name: Option<String>,
+        actions: Option<Vec<Action>>,
+        expires_at: Option<Option<DateTime<Utc>>>,
+    ) -> Option<Key> {
+        let mut keys = self.keys.lock().unwrap();
+        let key = keys.get_mut(&uid)?;
+
+        if let Some(name) = name {
+            key.name = Some(name);
+        }
+        if let Some(actions) = actions {
+            key.actions = actions;
+        }
+        if let Some(expires_at) = expires_at {
+            key.expires_at = expires_at;
+        }
+        key.updated_at = Utc::now();
+
+        Some(key.clone())
+    }
+
+    pub(super) fn delete_key(&self, uid: Uuid) -> bool {
+        self.keys.lock().unwrap().remove(&uid).is_some()
+    }
+
+    /// Resolve the [`Key`] that produced `token`, if any, rejecting expired keys.
+    ///
+    /// Tokens are compared in constant time so a request presenting an almost-correct token
+    /// cannot be distinguished, by response latency, from one presenting a wildly wrong one.
+    pub(super) fn authenticate(&self, token: &str) -> Option<Key> {
+        let key = self
+            .keys
+            .lock()
+            .unwrap()
+            .values()
+            .find(|key| {
+                self.token_for(key.uid)
+                    .as_bytes()
+                    .ct_eq(token.as_bytes())
+                    .into()
+            })
+            .cloned()?;
+
+        match key.expires_at {
+            Some(expires_at) if expires_at <= Utc::now() => None,
+            _ => Some(key),
+        }
+    }
+
+    /// Returns the bearer token to present for the given key, e.g. right after creating it.
+    pub(super) fn reveal_token(&self, key: &Key) -> String {
+        self.token_for(key.uid)
+    }
+
+    fn authorize(&self, token: &str, required: Action) -> bool {
+        match self.authenticate(token) {
+            Some(key) => key.actions.contains(&required) || key.actions.contains(&Action::All),
+            None => false,
+        }
+    }
+}
+
+pub(super) fn bearer_token(req: &HttpRequest) -> Option<String> {
+    let value = req.headers().get(header::AUTHORIZATION)?.to_str().ok()?;
+
+    value.strip_prefix("Bearer ").map(str::to_owned)
+}
+
+/// Whether `req`'s bearer token grants `SecretsReveal`, independent of whichever policy
+/// already gated the route via [`GuardedData`]. Config handlers use this to decide between
+/// returning a decrypted secret value or the redacted `"********"` placeholder.
+pub(super) fn can_reveal_secrets(req: &HttpRequest) -> bool {
+    match req.app_data::<Data<AuthConfig>>().map(|data| data.get_ref()) {
+        None | Some(AuthConfig::NoAuth) => true,
+        Some(AuthConfig::Auth(policies)) => {
+            policies.authenticate::<CanRevealSecrets>(bearer_token(req).as_deref())
+        }
+    }
+}
+
+/// A check a [`GuardedData`] extractor runs against the presented bearer token.
+///
+/// Implementations are looked up by the marker type used as `GuardedData`'s `P` parameter, not
+/// called directly, so route handlers only ever name the marker (e.g. [`CanManageKeys`]).
+trait Policy: Send + Sync {
+    fn authenticate(&self, token: Option<&str>) -> bool;
+}
+
+/// Always authorizes; used for routes with no auth requirement.
+struct PublicPolicy;
+
+impl Policy for PublicPolicy {
+    fn authenticate(&self, _token: Option<&str>) -> bool {
+        true
+    }
+}
+
+/// Authorizes any request presenting a valid, non-expired key, regardless of its actions.
+struct AuthenticatedPolicy {
+    auth: Data<AuthController>,
+}
+
+impl Policy for AuthenticatedPolicy {
+    fn authenticate(&self, token: Option<&str>) -> bool {
+        token.is_some_and(|token| self.auth.authenticate(token).is_some())
+    }
+}
+
+/// Authorizes requests whose key grants a specific [`Action`].
+struct ActionPolicy {
+    auth: Data<AuthController>,
+    action: Action,
+}
+
+impl Policy for ActionPolicy {
+    fn authenticate(&self, token: Option<&str>) -> bool {
+        token.is_some_and(|token| self.auth.authorize(token, self.action))
+    }
+}
+
+/// Marker type for [`GuardedData`]: any request with a valid, non-expired key is allowed in.
+pub(super) struct Authenticated;
+/// Marker type for [`GuardedData`]: no auth is required at all.
+pub(super) struct Public;
+/// Marker type for [`GuardedData`]: requires the `ConfigsGet` action.
+pub(super) struct CanGetConfigs;
+/// Marker type for [`GuardedData`]: requires the `ConfigsCreate` action.
+pub(super) struct CanCreateConfigs;
+/// Marker type for [`GuardedData`]: requires the `ConfigsUpdate` action.
+pub(super) struct CanUpdateConfigs;
+/// Marker type for [`GuardedData`]: requires the `ConfigsDelete` action.
+pub(super) struct CanDeleteConfigs;
+/// Marker type for [`GuardedData`]: requires the `Search` action.
+pub(super) struct CanSearch;
+/// Marker type for [`GuardedData`]: requires the `KeysManage` action.
+pub(super) struct CanManageKeys;
+/// Marker type for [`GuardedData`]: requires the `SecretsReveal` action.
+pub(super) struct CanRevealSecrets;
+
+/// Registry of [`Policy`] implementations, keyed by the [`TypeId`] of the marker type a
+/// [`GuardedData`] extractor is parameterized with.
+pub(super) struct Policies(HashMap<TypeId, Box<dyn Policy>>);
+
+impl Policies {
+    /// Build the registry once at startup from the shared [`AuthController`].
+    pub(super) fn new(auth: Data<AuthController>) -> Self {
+        let mut policies: HashMap<TypeId, Box<dyn Policy>> = HashMap::new();
+
+        policies.insert(TypeId::of::<Public>(), Box::new(PublicPolicy));
+        policies.insert(
+            TypeId::of::<Authenticated>(),
+            Box::new(AuthenticatedPolicy { auth: auth.clone() }),
+        );
+
+        for (type_id, action) in [
+            (TypeId::of::<CanGetConfigs>(), Action::ConfigsGet),
+            (TypeId::of::<CanCreateConfigs>(), Action::ConfigsCreate),
+            (TypeId::of::<CanUpdateConfigs>(), Action::ConfigsUpdate),
+            (TypeId::of::<CanDeleteConfigs>(), Action::ConfigsDelete),
+            (TypeId::of::<CanSearch>(), Action::Search),
+            (TypeId::of::<CanManageKeys>(), Action::KeysManage),
+            (TypeId::of::<CanRevealSecrets>(), Action::SecretsReveal),
+        ] {
+            policies.insert(
+                type_id,
+                Box::new(ActionPolicy {
+                    auth: auth.clone(),
+                    action,
+                }),
+            );
+        }
+
+        Self(policies)
+    }
+
+    fn authenticate<P: 'static>(&self, token: Option<&str>) -> bool {
+        self.0
+            .get(&TypeId::of::<P>())
+            .unwrap_or_else(|| panic!("no policy registered for {}", std::any::type_name::<P>()))
+            .authenticate(token)
+    }
+}
+
+/// Selects whether routes guarded by [`GuardedData`] are checked against [`Policies`] at all.
+///
+/// `NoAuth` is meant for local development, where standing up a key store is unnecessary
+/// friction; production deployments use `Auth`.
+pub(super) enum AuthConfig {
+    NoAuth,
+    Auth(Policies),
+}
+
+/// Extractor that authorizes a request against policy `P` before yielding the wrapped `T`.
+///
+/// Replaces the previous `RequireApiKey`/`LogApiKey` middleware: the required policy is now part of a
+/// handler's signature, e.g. `config_store: GuardedData<CanDeleteConfigs, Data<ConfigStore>>`,
+/// so authorization is visible at the call site instead of hidden behind a route attribute.
+pub(super) struct GuardedData<P, T> {
+    data: T,
+    _policy: PhantomData<P>,
+}
+
+impl<P, T> Deref for GuardedData<P, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.data
+    }
+}
+
+impl<P, T> FromRequest for GuardedData<P, T>
+where
+    P: 'static,
+    T: FromRequest + 'static,
+{
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let auth_config = req.app_data::<Data<AuthConfig>>().cloned();
+        let token = bearer_token(req);
+        let data_fut = T::from_request(req, payload);
+
+        Box::pin(async move {
+            let authorized = match auth_config.as_deref() {
+                None | Some(AuthConfig::NoAuth) => true,
+                Some(AuthConfig::Auth(policies)) => {
+                    policies.authenticate::<P>(token.as_deref())
+                }
+            };
+
+            if !authorized {
+                return Err(
+                    ErrorResponse::unauthorized(String::from("missing or invalid api key")).into(),
+                );
+            }
+
+            let data = data_fut.await?;
+
+            Ok(GuardedData {
+                data,
+                _policy: PhantomData,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derive_token_is_deterministic_per_master_key_and_uid() {
+        let uid = Uuid::new_v4();
+
+        assert_eq!(derive_token(b"master", uid), derive_token(b"master", uid));
+    }
+
+    #[test]
+    fn derive_token_differs_across_master_keys() {
+        let uid = Uuid::new_v4();
+
+        assert_ne!(derive_token(b"master", uid), derive_token(b"other", uid));
+    }
+
+    #[test]
+    fn authenticates_a_key_with_its_derived_token() {
+        let auth = AuthController::new(b"master".to_vec());
+        let key = auth.create_key(None, vec![Action::ConfigsGet], None);
+        let token = auth.reveal_token(&key);
+
+        assert_eq!(auth.authenticate(&token).map(|key| key.uid), Some(key.uid));
+    }
+
+    #[test]
+    fn rejects_an_unknown_token() {
+        let auth = AuthController::new(b"master".to_vec());
+
+        assert!(auth.authenticate("not-a-real-token").is_none());
+    }
+
+    #[test]
+    fn rejects_an_expired_key() {
+        let auth = AuthController::new(b"master".to_vec());
+        let key = auth.create_key(
+            None,
+            vec![Action::ConfigsGet],
+            Some(Utc::now() - chrono::Duration::seconds(1)),
+        );
+        let token = auth.reveal_token(&key);
+
+        assert!(auth.authenticate(&token).is_none());
+    }
+
+    #[test]
+    fn authorizes_only_keys_granting_the_required_action_or_all() {
+        let auth = AuthController::new(b"master".to_vec());
+        let scoped = auth.create_key(None, vec![Action::ConfigsGet], None);
+        let admin = auth.create_key(None, vec![Action::All], None);
+
+        assert!(auth.authorize(&auth.reveal_token(&scoped), Action::ConfigsGet));
+        assert!(!auth.authorize(&auth.reveal_token(&scoped), Action::ConfigsDelete));
+        assert!(auth.authorize(&auth.reveal_token(&admin), Action::ConfigsDelete));
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+
+use handlebars::Handlebars;
+
+/// Why resolving a templated config value failed.
+pub(super) enum RenderError {
+    /// A `{{ key }}` placeholder referenced a key with no stored config. Also returned, naming
+    /// the config being rendered, when Handlebars fails to render that config's template.
+    UnknownKey(String),
+    /// Resolving `key` would require resolving `key` again, i.e. a reference cycle.
+    Cycle(String),
+}
+
+/// Resolve `{{ other_key }}` placeholders in `root_value`, the value of the config stored under
+/// `root_key`, looking referenced keys up in `context` (every stored config's `key -> value`).
+///
+/// `root_value` is taken as-is rather than re-looked-up from `context` by `root_key`, since
+/// nothing enforces `key` uniqueness across configs: `context` only reliably identifies a config
+/// when used for a *referenced* key, not for the specific config being rendered.
+///
+/// References chain (`a -> b -> c`), so each referenced key is itself resolved before
+/// substitution. Diamonds (`a` references both `b` and `c`, `c` also references `b`) are fine;
+/// only a key depending on itself, directly or transitively, is rejected as a cycle.
+pub(super) fn resolve(
+    context: &HashMap<String, String>,
+    root_key: &str,
+    root_value: &str,
+) -> Result<String, RenderError> {
+    let mut memo = HashMap::new();
+    render(context, root_key, root_value, &mut vec![root_key.to_owned()], &mut memo)
+}
+
+/// Resolve a single referenced `key` by looking its template up in `context`, tracking the keys
+/// currently being resolved in `path` (to detect cycles) and caching resolved values in `memo`
+/// (so a key referenced more than once, as in a diamond, is only rendered once).
+fn resolve_key(
+    context: &HashMap<String, String>,
+    key: &str,
+    path: &mut Vec<String>,
+    memo: &mut HashMap<String, String>,
+) -> Result<String, RenderError> {
+    if let Some(resolved) = memo.get(key) {
+        return Ok(resolved.clone());
+    }
+    if path.iter().any(|ancestor| ancestor == key) {
+        return Err(RenderError::Cycle(key.to_owned()));
+    }
+
+    let template = context
+        .get(key)
+        .ok_or_else(|| RenderError::UnknownKey(key.to_owned()))?
+        .clone();
+
+    path.push(key.to_owned());
+    let resolved = render(context, key, &template, path, memo)?;
+    path.pop();
+
+    memo.insert(key.to_owned(), resolved.clone());
+
+    Ok(resolved)
+}
+
+/// Render `template` (the value stored under `key`), resolving every placeholder it references.
+fn render(
+    context: &HashMap<String, String>,
+    key: &str,
+    template: &str,
+    path: &mut Vec<String>,
+    memo: &mut HashMap<String, String>,
+) -> Result<String, RenderError> {
+    let mut substitutions = HashMap::new();
+    for referenced in referenced_keys(template) {
+        let value = resolve_key(context, &referenced, path, memo)?;
+        substitutions.insert(referenced, value);
+    }
+
+    Handlebars::new()
+        .render_template(template, &substitutions)
+        .map_err(|_| RenderError::UnknownKey(key.to_owned()))
+}
+
+/// Extract the `key` out of every `{{ key }}` placeholder in `template`, in order of appearance.
+fn referenced_keys(template: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+
+        keys.push(after[..end].trim().to_owned());
+        rest = &after[end + 2..];
+    }
+
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn resolves_a_chain_of_references() {
+        let context = context(&[("a", "{{b}}"), ("b", "{{c}}"), ("c", "leaf")]);
+
+        assert_eq!(resolve(&context, "a", "{{b}}").unwrap(), "leaf");
+    }
+
+    #[test]
+    fn resolves_a_diamond_without_flagging_a_cycle() {
+        // a -> b, a -> c, c -> b: `b` is reachable two ways but never depends on itself.
+        let context = context(&[("a", "{{b}} {{c}}"), ("b", "bbb"), ("c", "{{b}}!")]);
+
+        assert_eq!(resolve(&context, "a", "{{b}} {{c}}").unwrap(), "bbb bbb!");
+    }
+
+    #[test]
+    fn rejects_a_direct_self_reference() {
+        let context = context(&[("a", "{{a}}")]);
+
+        assert!(matches!(
+            resolve(&context, "a", "{{a}}"),
+            Err(RenderError::Cycle(key)) if key == "a"
+        ));
+    }
+
+    #[test]
+    fn rejects_an_indirect_cycle() {
+        let context = context(&[("a", "{{b}}"), ("b", "{{a}}")]);
+
+        assert!(matches!(
+            resolve(&context, "a", "{{b}}"),
+            Err(RenderError::Cycle(key)) if key == "a"
+        ));
+    }
+
+    #[test]
+    fn rejects_a_reference_to_an_unknown_key() {
+        let context = context(&[]);
+
+        assert!(matches!(
+            resolve(&context, "a", "{{missing}}"),
+            Err(RenderError::UnknownKey(key)) if key == "missing"
+        ));
+    }
+}
@@ -0,0 +1,70 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use sha2::{Digest, Sha256};
+
+const PART_SEPARATOR: char = ':';
+
+/// Derive a 32-byte AES-256 key from an arbitrary-length passphrase, mirroring how
+/// `AuthController` accepts a master key of any length for its HMAC.
+pub(super) fn derive_key(passphrase: &[u8]) -> [u8; 32] {
+    Sha256::digest(passphrase).into()
+}
+
+/// Encrypt `plaintext`, returning `hex(nonce):hex(ciphertext)` for storage at rest.
+pub(super) fn encrypt(key: &[u8; 32], plaintext: &str) -> String {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_bytes())
+        .expect("AES-256-GCM encryption with a fresh nonce cannot fail");
+
+    format!("{}{PART_SEPARATOR}{}", hex::encode(nonce), hex::encode(ciphertext))
+}
+
+/// Decrypt a value previously produced by [`encrypt`]. Returns `None` on any malformed or
+/// tampered input rather than panicking.
+pub(super) fn decrypt(key: &[u8; 32], encoded: &str) -> Option<String> {
+    let (nonce, ciphertext) = encoded.split_once(PART_SEPARATOR)?;
+    let nonce = hex::decode(nonce).ok()?;
+    let ciphertext = hex::decode(ciphertext).ok()?;
+    // AES-GCM nonces are 96 bits; `Nonce::from_slice` panics on any other length.
+    if nonce.len() != 12 {
+        return None;
+    }
+
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+        .ok()?;
+
+    String::from_utf8(plaintext).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_encrypt_and_decrypt() {
+        let key = derive_key(b"passphrase");
+        let encrypted = encrypt(&key, "top secret value");
+
+        assert_eq!(decrypt(&key, &encrypted).as_deref(), Some("top secret value"));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let key = derive_key(b"passphrase");
+
+        assert_eq!(decrypt(&key, "not-a-valid-encoded-value"), None);
+    }
+
+    #[test]
+    fn rejects_ciphertext_decrypted_with_the_wrong_key() {
+        let encrypted = encrypt(&derive_key(b"passphrase"), "top secret value");
+
+        assert_eq!(decrypt(&derive_key(b"a different passphrase"), &encrypted), None);
+    }
+}
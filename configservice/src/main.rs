@@ -1,31 +1,18 @@
-use std::{
-    error::Error,
-    future::{self, Ready},
-    net::Ipv4Addr,
-};
+use std::{error::Error, net::Ipv4Addr};
 
 use env_logger::Env;
 
-use actix_web::{
-    dev::{Service, ServiceRequest, ServiceResponse, Transform},
-    middleware::Logger,
-    web::Data,
-    App, HttpResponse, HttpServer,
-};
-use futures::future::LocalBoxFuture;
+use actix_web::{middleware::Logger, web::Data, App, HttpServer};
 use utoipa::{
     openapi::security::{ApiKey, ApiKeyValue, SecurityScheme},
     Modify, OpenApi,
 };
 use utoipa_swagger_ui::SwaggerUi;
 
-use crate::configservice::{ErrorResponse, ConfigStore};
+use crate::configservice::{AuthConfig, AuthController, ConfigStore, Policies};
 
 mod configservice;
 
-const API_KEY_NAME: &str = "apikey";
-const API_KEY: &str = "rust-rocks";
-
 #[actix_web::main]
 async fn main() -> Result<(), impl Error> {
     env_logger::init_from_env(Env::default().default_filter_or("info"));
@@ -33,18 +20,34 @@ async fn main() -> Result<(), impl Error> {
     #[derive(OpenApi)]
     #[openapi(
         paths(
+            configservice::health,
             configservice::get_configs,
             configservice::create_config,
             configservice::delete_config,
             configservice::get_config_by_id,
+            configservice::render_config,
             configservice::update_config,
-            configservice::search_configs
+            configservice::search_configs,
+            configservice::whoami,
+            configservice::list_keys,
+            configservice::create_key,
+            configservice::update_key,
+            configservice::delete_key
         ),
         components(
-            schemas(configservice::Config, configservice::ConfigUpdateRequest, configservice::ErrorResponse)
+            schemas(
+                configservice::Config,
+                configservice::ConfigUpdateRequest,
+                configservice::ErrorResponse,
+                configservice::Key,
+                configservice::CreateKeyRequest,
+                configservice::UpdateKeyRequest,
+                configservice::KeyResponse
+            )
         ),
         tags(
-            (name = "config", description = "Configuration management endpoints.")
+            (name = "config", description = "Configuration management endpoints."),
+            (name = "keys", description = "API key management endpoints.")
         ),
         modifiers(&SecurityAddon)
     )]
@@ -57,12 +60,22 @@ async fn main() -> Result<(), impl Error> {
             let components = openapi.components.as_mut().unwrap(); // we can unwrap safely since there already is components registered.
             components.add_security_scheme(
                 "api_key",
-                SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("todo_apikey"))),
+                SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("Authorization"))),
             )
         }
     }
 
-    let store = Data::new(ConfigStore::default());
+    let secret_key = std::env::var("SECRET_ENCRYPTION_KEY")
+        .unwrap_or_else(|_| String::from("rust-rocks-secret"));
+    let store = Data::new(ConfigStore::new(secret_key));
+    let master_key = std::env::var("MASTER_KEY").unwrap_or_else(|_| String::from("rust-rocks"));
+    let auth = Data::new(AuthController::new(master_key));
+    // `DISABLE_AUTH=1` skips policy checks entirely; meant for local development only.
+    let auth_config = Data::new(if std::env::var("DISABLE_AUTH").is_ok() {
+        AuthConfig::NoAuth
+    } else {
+        AuthConfig::Auth(Policies::new(auth.clone()))
+    });
     // Make instance variable of ApiDoc so all worker threads gets the same instance.
     let openapi = ApiDoc::openapi();
 
@@ -70,7 +83,11 @@ async fn main() -> Result<(), impl Error> {
         // This factory closure is called on each worker thread independently.
         App::new()
             .wrap(Logger::default())
-            .configure(configservice::configure(store.clone()))
+            .configure(configservice::configure(
+                store.clone(),
+                auth.clone(),
+                auth_config.clone(),
+            ))
             .service(
                 SwaggerUi::new("/swagger-ui/{_:.*}").url("/api-doc/openapi.json", openapi.clone()),
             )
@@ -79,126 +96,3 @@ async fn main() -> Result<(), impl Error> {
     .run()
     .await
 }
-
-/// Require api key middleware will actually require valid api key
-struct RequireApiKey;
-
-impl<S> Transform<S, ServiceRequest> for RequireApiKey
-where
-    S: Service<
-        ServiceRequest,
-        Response = ServiceResponse<actix_web::body::BoxBody>,
-        Error = actix_web::Error,
-    >,
-    S::Future: 'static,
-{
-    type Response = ServiceResponse<actix_web::body::BoxBody>;
-    type Error = actix_web::Error;
-    type Transform = ApiKeyMiddleware<S>;
-    type InitError = ();
-    type Future = Ready<Result<Self::Transform, Self::InitError>>;
-
-    fn new_transform(&self, service: S) -> Self::Future {
-        future::ready(Ok(ApiKeyMiddleware {
-            service,
-            log_only: false,
-        }))
-    }
-}
-
-/// Log api key middleware only logs about missing or invalid api keys
-struct LogApiKey;
-
-impl<S> Transform<S, ServiceRequest> for LogApiKey
-where
-    S: Service<
-        ServiceRequest,
-        Response = ServiceResponse<actix_web::body::BoxBody>,
-        Error = actix_web::Error,
-    >,
-    S::Future: 'static,
-{
-    type Response = ServiceResponse<actix_web::body::BoxBody>;
-    type Error = actix_web::Error;
-    type Transform = ApiKeyMiddleware<S>;
-    type InitError = ();
-    type Future = Ready<Result<Self::Transform, Self::InitError>>;
-
-    fn new_transform(&self, service: S) -> Self::Future {
-        future::ready(Ok(ApiKeyMiddleware {
-            service,
-            log_only: true,
-        }))
-    }
-}
-
-struct ApiKeyMiddleware<S> {
-    service: S,
-    log_only: bool,
-}
-
-impl<S> Service<ServiceRequest> for ApiKeyMiddleware<S>
-where
-    S: Service<
-        ServiceRequest,
-        Response = ServiceResponse<actix_web::body::BoxBody>,
-        Error = actix_web::Error,
-    >,
-    S::Future: 'static,
-{
-    type Response = ServiceResponse<actix_web::body::BoxBody>;
-    type Error = actix_web::Error;
-    type Future = LocalBoxFuture<'static, Result<Self::Response, actix_web::Error>>;
-
-    fn poll_ready(
-        &self,
-        ctx: &mut core::task::Context<'_>,
-    ) -> std::task::Poll<Result<(), Self::Error>> {
-        self.service.poll_ready(ctx)
-    }
-
-    fn call(&self, req: ServiceRequest) -> Self::Future {
-        let response = |req: ServiceRequest, response: HttpResponse| -> Self::Future {
-            Box::pin(async { Ok(req.into_response(response)) })
-        };
-
-        match req.headers().get(API_KEY_NAME) {
-            Some(key) if key != API_KEY => {
-                if self.log_only {
-                    log::debug!("Incorrect api api provided!!!")
-                } else {
-                    return response(
-                        req,
-                        HttpResponse::Unauthorized().json(ErrorResponse::Unauthorized(
-                            String::from("incorrect api key"),
-                        )),
-                    );
-                }
-            }
-            None => {
-                if self.log_only {
-                    log::debug!("Missing api key!!!")
-                } else {
-                    return response(
-                        req,
-                        HttpResponse::Unauthorized()
-                            .json(ErrorResponse::Unauthorized(String::from("missing api key"))),
-                    );
-                }
-            }
-            _ => (), // just passthrough
-        }
-
-        if self.log_only {
-            log::debug!("Performing operation")
-        }
-
-        let future = self.service.call(req);
-
-        Box::pin(async move {
-            let response = future.await?;
-
-            Ok(response)
-        })
-    }
-}
\ No newline at end of file